@@ -1,25 +1,56 @@
-/// Returns the ceiling of the square root of an unsigned integer in a
-/// timely manner, using binary search.  
+/// Returns `floor(n^(1/k))`, the largest `x` such that `x^k <= n`.
+///
+/// Finds an initial estimate `x = 2^(ceil(bits(n)/k))` (guaranteed at
+/// least as large as the true root, since it rounds the bit count up),
+/// then refines it via Newton's method, `x = ((k-1)*x + n/x^(k-1)) / k`,
+/// until it stops decreasing. Every `x.pow(k-1)` and the Newton-step
+/// arithmetic itself are computed through checked operations; overflow
+/// in either means the current `x` is already small enough that no
+/// further refinement is needed, so it's accepted as-is (for `pow`,
+/// after first halving `x` until the power fits).
+///
+/// # Panics
+/// Panics if `k` is 0.
 /// Time complexity: O(log(n))
-fn usqrt(n: u64) -> u64 {
-    let (mut low, mut high) = (1, n);
-    let mut mid = (low + high) / 2;
-    while low < high {
-        mid = (low + high) / 2;
-        let square = mid * mid;
-        if square == n {
-            return mid;
-        } else if square > n {
-            high = mid - 1
-        } else {
-            low = mid + 1
+pub fn iroot(n: u64, k: u32) -> u64 {
+    assert!(k >= 1, "k must be at least 1");
+    if k == 1 || n == 0 {
+        return n;
+    }
+    let bits = u64::BITS - n.leading_zeros();
+    let mut x = 1u64 << bits.div_ceil(k);
+    loop {
+        let x_pow = loop {
+            match x.checked_pow(k - 1) {
+                Some(p) if p != 0 => break p,
+                _ => x /= 2,
+            }
+        };
+        let next = match (k as u64 - 1)
+            .checked_mul(x)
+            .and_then(|s| s.checked_add(n / x_pow))
+        {
+            Some(sum) => sum / k as u64,
+            None => break,
+        };
+        if next >= x {
+            break;
         }
+        x = next;
     }
-    if mid * mid == n {
-        mid
-    } else {
-        high
+    while x.checked_pow(k).is_none_or(|p| p > n) {
+        x -= 1;
     }
+    while (x + 1).checked_pow(k).is_some_and(|p| p <= n) {
+        x += 1;
+    }
+    x
+}
+
+/// Returns `floor(sqrt(n))`.
+/// Time complexity: O(log(n))
+fn usqrt(n: u64) -> u64 {
+    iroot(n, 2)
 }
 
 #[cfg(test)]
@@ -53,31 +84,333 @@ mod usqrt_tests {
     }
 
     #[test]
-    fn rounds_up_when_not_a_perfect_square() {
-        assert_eq!(usqrt(2), 2);
+    fn rounds_down_when_not_a_perfect_square() {
+        assert_eq!(usqrt(2), 1);
     }
 
     #[test]
     fn large_not_perfect_square() {
         let x = 12345;
-        assert_eq!(usqrt(x * x + 1), x + 1)
+        assert_eq!(usqrt(x * x + 1), x)
+    }
+}
+
+#[cfg(test)]
+mod iroot_tests {
+    use super::iroot;
+
+    #[test]
+    fn square_root_matches_usqrt_semantics() {
+        assert_eq!(iroot(0, 2), 0);
+        assert_eq!(iroot(1, 2), 1);
+        assert_eq!(iroot(99, 2), 9);
+        assert_eq!(iroot(100, 2), 10);
+        assert_eq!(iroot(101, 2), 10);
+    }
+
+    #[test]
+    fn cube_root_of_perfect_and_imperfect_cubes() {
+        assert_eq!(iroot(27, 3), 3);
+        assert_eq!(iroot(26, 3), 2);
+        assert_eq!(iroot(28, 3), 3);
+    }
+
+    #[test]
+    fn kth_root_of_a_perfect_power_near_u64_max() {
+        // An exact and large power: 3^40 fits in u64 and is a perfect
+        // 40th power of 3.
+        let n = 3u64.pow(40);
+        assert_eq!(iroot(n, 40), 3);
+    }
+
+    #[test]
+    fn first_root_is_identity() {
+        assert_eq!(iroot(12345, 1), 12345);
+    }
+
+    #[test]
+    fn large_k_with_n_near_u64_max_does_not_overflow() {
+        // k large enough that the Newton step's estimate collapses to
+        // x = 1, at which point `n / x_pow == n` and adding `k - 1` to
+        // it must not overflow.
+        assert_eq!(iroot(u64::MAX, 100), 1);
+    }
+
+    #[test]
+    fn detects_perfect_powers_as_a_composite_pre_filter() {
+        // 1024 = 2^10 = 4^5 = 32^2, so it is detected as a perfect
+        // power (hence composite) by more than one exponent.
+        assert_eq!(iroot(1024, 10), 2);
+        assert_eq!(iroot(1024, 5), 4);
+        assert_eq!(iroot(1024, 2), 32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_zeroth_root() {
+        iroot(8, 0);
+    }
+}
+
+/// Multiplies `a` and `b` modulo `m`, routing through `u128` so the
+/// product can never overflow `u64`.
+fn mulmod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// Computes `base.pow(exp) % m`, squaring at each step and reducing
+/// through [`mulmod`] to stay within `u64`.
+fn powmod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mulmod(result, base, m);
+        }
+        base = mulmod(base, base, m);
+        exp >>= 1;
     }
+    result
+}
+
+/// Montgomery-form modular arithmetic for a fixed odd modulus `n`.
+///
+/// Converting operands into Montgomery form trades the `u128` remainder
+/// in [`mulmod`] for a shift and a couple of `u64` multiplications,
+/// which roughly halves the cost of the repeated squarings in
+/// [`is_prime`]'s witness loop.
+struct Montgomery {
+    n: u64,
+    /// The negated modular inverse of `n`, i.e. `n * ni ≡ -1 (mod 2^64)`.
+    /// Negated (rather than the plain inverse Newton's method produces)
+    /// so that `t = (T + (T * ni mod 2^64) * n) / 2^64` comes out to an
+    /// exact multiple of `2^64`, which is what makes the reduction work.
+    ni: u64,
+    /// `2^128 mod n`, used to move operands into Montgomery form.
+    r2: u64,
+    /// Odd part of `n - 1`, i.e. `n - 1 = d * 2^s`.
+    d: u64,
+    /// `s` such that `n - 1 = d * 2^s`.
+    s: u32,
 }
 
-/// Determines whether a number is prime or not.  
-/// Time complexity: O(sqrt(n))
+impl Montgomery {
+    /// Builds a Montgomery context for the odd modulus `n`.
+    fn new(n: u64) -> Self {
+        debug_assert!(n % 2 == 1);
+        // Newton's method on the 2-adic inverse: starting from `ni = n`
+        // (correct to 3 bits, since `n * n ≡ 1 (mod 8)` for odd `n`), each
+        // of the 5 iterations doubles the number of correct bits, so
+        // 3 -> 96 covers all 64.
+        let mut ni = n;
+        for _ in 0..5 {
+            ni = ni.wrapping_mul(2u64.wrapping_sub(n.wrapping_mul(ni)));
+        }
+        let ni = ni.wrapping_neg();
+        let r2 = (((1u128 << 64) % n as u128).pow(2) % n as u128) as u64;
+        let mut d = n - 1;
+        let mut s = 0;
+        while d.is_multiple_of(2) {
+            d /= 2;
+            s += 1;
+        }
+        Montgomery { n, ni, r2, d, s }
+    }
+
+    /// Montgomery reduction: given `a`, `b` already in Montgomery form,
+    /// returns `a * b * r^-1 mod n`, still in Montgomery form.
+    fn mrmul(&self, a: u64, b: u64) -> u64 {
+        let t = a as u128 * b as u128;
+        let m = (t as u64).wrapping_mul(self.ni);
+        let t = (t + m as u128 * self.n as u128) >> 64;
+        let t = t as u64;
+        if t >= self.n {
+            t - self.n
+        } else {
+            t
+        }
+    }
+
+    /// Moves `a` into Montgomery form.
+    fn to_mont(&self, a: u64) -> u64 {
+        self.mrmul(a, self.r2)
+    }
+
+    /// Moves `ar`, a value in Montgomery form, back to a plain integer.
+    // Only called from tests, which round-trip values back to plain
+    // integers to check them against a non-Montgomery reference;
+    // `is_composite_witness` itself only ever compares values while
+    // they're still in Montgomery form.
+    #[allow(dead_code)]
+    fn demont(&self, ar: u64) -> u64 {
+        self.mrmul(ar, 1)
+    }
+
+    /// Adds two values in Montgomery form modulo `n`.
+    // Only called from tests; the witness loop needs no modular
+    // addition.
+    #[allow(dead_code)]
+    fn addmod(&self, a: u64, b: u64) -> u64 {
+        let (sum, overflowed) = a.overflowing_add(b);
+        if overflowed || sum >= self.n {
+            sum.wrapping_sub(self.n)
+        } else {
+            sum
+        }
+    }
+
+    /// Subtracts `b` from `a` modulo `n`, both in Montgomery form.
+    #[allow(dead_code)]
+    fn submod(&self, a: u64, b: u64) -> u64 {
+        if a >= b {
+            a - b
+        } else {
+            a + self.n - b
+        }
+    }
+
+    /// Computes `base^exp mod n`, where `base` is already in Montgomery
+    /// form and the result is returned in Montgomery form as well.
+    fn powmod(&self, mut base: u64, mut exp: u64) -> u64 {
+        let mut result = self.to_mont(1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mrmul(result, base);
+            }
+            base = self.mrmul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod montgomery_tests {
+    use super::Montgomery;
+
+    #[test]
+    fn round_trips_through_mont_form() {
+        let mont = Montgomery::new(97);
+        for a in 0..97 {
+            assert_eq!(mont.demont(mont.to_mont(a)), a);
+        }
+    }
+
+    #[test]
+    fn powmod_matches_naive_exponentiation() {
+        let n = 1_000_000_007u64;
+        let mont = Montgomery::new(n);
+        for base in [2u64, 3, 12345, 999_999_999] {
+            let expected = super::powmod(base, 1000, n);
+            let actual = mont.demont(mont.powmod(mont.to_mont(base), 1000));
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn addmod_matches_naive_modular_addition() {
+        let n = 97u64;
+        let mont = Montgomery::new(n);
+        for a in 0..n {
+            for b in 0..n {
+                let expected = (a + b) % n;
+                let actual = mont.demont(mont.addmod(mont.to_mont(a), mont.to_mont(b)));
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn submod_matches_naive_modular_subtraction() {
+        let n = 97u64;
+        let mont = Montgomery::new(n);
+        for a in 0..n {
+            for b in 0..n {
+                let expected = (a + n - b) % n;
+                let actual = mont.demont(mont.submod(mont.to_mont(a), mont.to_mont(b)));
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+}
+
+/// Returns `true` if `base` witnesses that `n` is composite, using
+/// Montgomery-form arithmetic for the modular exponentiation.
+fn is_composite_witness(base: u64, mont: &Montgomery) -> bool {
+    let one = mont.to_mont(1);
+    let n_minus_one = mont.to_mont(mont.n - 1);
+    let mut x = mont.powmod(mont.to_mont(base), mont.d);
+    if x == one || x == n_minus_one {
+        return false;
+    }
+    for _ in 1..mont.s {
+        x = mont.mrmul(x, x);
+        if x == n_minus_one {
+            return false;
+        }
+    }
+    true
+}
+
+/// Same test as [`is_composite_witness`], but through the plain
+/// `u128`-remainder [`powmod`]/[`mulmod`] instead of Montgomery form.
+///
+/// [`Montgomery::mrmul`] adds two `u128` products together, which
+/// overflows once `n` gets close enough to `u64::MAX`; this fallback
+/// keeps `is_prime` correct across all of `u64` at the cost of the
+/// Montgomery speedup for that narrow top range.
+fn is_composite_witness_plain(base: u64, d: u64, s: u32, n: u64) -> bool {
+    let mut x = powmod(base, d, n);
+    if x == 1 || x == n - 1 {
+        return false;
+    }
+    for _ in 1..s {
+        x = mulmod(x, x, n);
+        if x == n - 1 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Above this modulus, [`Montgomery::mrmul`]'s `u128` addition can
+/// overflow (`T + m*n` approaches `2 * n * 2^64`), so [`is_prime`] falls
+/// back to the plain `u128`-remainder witness test beyond it.
+const MONTGOMERY_SAFE_LIMIT: u64 = 1 << 63;
+
+/// Determines whether a number is prime or not, using a deterministic
+/// Miller–Rabin test.
+/// Time complexity: O(log^3(n))
 fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
     if n == 2 || n == 3 {
         return true;
     }
-    if n % 2 == 0 || n <= 1 {
+    if n.is_multiple_of(2) {
         return false;
     }
-    let lower = 3;
-    let upper = usqrt(n);
-    (lower..(upper + 1))
-        .step_by(2)
-        .all(|maybe_divisor| n % maybe_divisor != 0)
+    // Sufficient to correctly decide primality for every n < 2^64.
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < MONTGOMERY_SAFE_LIMIT {
+        let mont = Montgomery::new(n);
+        !WITNESSES
+            .iter()
+            .filter(|&&base| base < n)
+            .any(|&base| is_composite_witness(base, &mont))
+    } else {
+        let mut d = n - 1;
+        let mut s = 0;
+        while d.is_multiple_of(2) {
+            d /= 2;
+            s += 1;
+        }
+        !WITNESSES
+            .iter()
+            .filter(|&&base| base < n)
+            .any(|&base| is_composite_witness_plain(base, d, s, n))
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +429,37 @@ mod is_prime_tests {
             vec![true; primes.len()]
         );
     }
+
+    #[test]
+    fn small_composites() {
+        let composites = vec![0, 1, 4, 6, 8, 9, 10, 15, 21, 25];
+        assert_eq!(
+            composites
+                .clone()
+                .into_iter()
+                .map(is_prime)
+                .collect::<Vec<bool>>(),
+            vec![false; composites.len()]
+        );
+    }
+
+    #[test]
+    fn large_prime_just_under_montgomery_safe_limit() {
+        // Exercises the Montgomery path right at the boundary where it
+        // hands off to the plain fallback, rather than only the
+        // trivially-small and trivially-large ends of the range.
+        assert!(is_prime((1u64 << 63) - 25));
+    }
+
+    #[test]
+    fn large_prime_near_u64_max() {
+        assert!(is_prime(18_446_744_073_709_551_557));
+    }
+
+    #[test]
+    fn large_composite_near_u64_max() {
+        assert!(!is_prime(18_446_744_073_709_551_615));
+    }
 }
 
 /// Finds the next prime number >= n.  
@@ -110,7 +474,7 @@ pub fn next_prime(mut n: u64) -> u64 {
     if n <= 2 {
         return 2;
     }
-    if n % 2 == 0 {
+    if n.is_multiple_of(2) {
         n += 1;
     }
     while !is_prime(n) {
@@ -150,3 +514,476 @@ mod next_prime_tests {
         assert_eq!(next_prime(472_888_178), 472_888_217)
     }
 }
+
+/// Returns the greatest common divisor of `a` and `b`.
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// A small, non-cryptographic xorshift PRNG used only to pick Pollard's
+/// rho's `c` constant; it has no bearing on correctness, only on how
+/// quickly a cycle is found.
+struct Xorshift64(u64);
+
+impl Xorshift64 {
+    fn new(seed: u64) -> Self {
+        Xorshift64(if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed })
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+/// Pollard's rho step function `f(x) = (x*x + c) mod n`.
+fn pollard_rho_step(x: u64, c: u64, n: u64) -> u64 {
+    ((mulmod(x, x, n) as u128 + c as u128) % n as u128) as u64
+}
+
+/// Finds a nontrivial factor of the composite `n` using Pollard's rho
+/// with Brent's cycle-detection improvement: `y` advances in doubling
+/// strides while `x` is the tortoise left behind at the start of each
+/// stride, and the `gcd` with `n` is taken once per batch of steps
+/// (instead of once per step) by accumulating the product of `|x - y|`.
+fn pollard_rho(n: u64, rng: &mut Xorshift64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+    const BATCH: u64 = 128;
+    loop {
+        let c = 1 + rng.next() % (n - 1);
+        let mut y = rng.next() % n;
+        let (mut x, mut ys) = (y, y);
+        let mut g = 1;
+        let mut stride = 1;
+        while g == 1 {
+            x = y;
+            for _ in 0..stride {
+                y = pollard_rho_step(y, c, n);
+            }
+            let mut done = 0;
+            while done < stride && g == 1 {
+                ys = y;
+                let batch = BATCH.min(stride - done);
+                let mut product = 1;
+                for _ in 0..batch {
+                    y = pollard_rho_step(y, c, n);
+                    product = mulmod(product, x.abs_diff(y), n);
+                }
+                g = gcd(product, n);
+                done += batch;
+            }
+            stride *= 2;
+        }
+        if g == n {
+            // The batched gcd overshot the factor; fall back to
+            // stepping one at a time from the last checkpoint.
+            loop {
+                ys = pollard_rho_step(ys, c, n);
+                g = gcd(x.abs_diff(ys), n);
+                if g > 1 {
+                    break;
+                }
+            }
+        }
+        if g != n {
+            return g;
+        }
+        // `c` was unlucky (e.g. produced a cycle covering all of n);
+        // the outer loop draws a fresh one and retries.
+    }
+}
+
+/// Recursively splits `n` into prime factors, pushing each onto `factors`.
+fn factorize_composite(n: u64, factors: &mut Vec<u64>) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        factors.push(n);
+        return;
+    }
+    let mut rng = Xorshift64::new(n);
+    let d = pollard_rho(n, &mut rng);
+    factorize_composite(d, factors);
+    factorize_composite(n / d, factors);
+}
+
+/// Strips all factors of `p` out of `n`, recording one entry in
+/// `factors` per factor found, and returns what remains of `n`.
+fn strip_factor(mut n: u64, p: u64, factors: &mut Vec<u64>) -> u64 {
+    while n.is_multiple_of(p) {
+        factors.push(p);
+        n /= p;
+    }
+    n
+}
+
+/// Trial-division primes used to cheaply strip small factors before
+/// falling back to Pollard's rho for the remaining cofactor.
+const SMALL_PRIMES: [u64; 61] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293,
+];
+
+/// Returns the prime factorization of `n`, with multiplicity, in
+/// ascending order.
+/// Time complexity: *expected* O(n^(1/4))
+/// # Examples
+/// ```
+/// use next_prime::factorize;
+/// assert_eq!(factorize(60), vec![2, 2, 3, 5]);
+/// assert_eq!(factorize(97), vec![97]);
+/// ```
+pub fn factorize(n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    if n < 2 {
+        return factors;
+    }
+    let mut n = strip_factor(n, 2, &mut factors);
+    for &p in SMALL_PRIMES.iter() {
+        if p * p > n {
+            break;
+        }
+        n = strip_factor(n, p, &mut factors);
+    }
+    factorize_composite(n, &mut factors);
+    factors.sort_unstable();
+    factors
+}
+
+#[cfg(test)]
+mod factorize_tests {
+    use super::factorize;
+
+    #[test]
+    fn factorizes_one_and_zero_as_empty() {
+        assert_eq!(factorize(0), Vec::<u64>::new());
+        assert_eq!(factorize(1), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn factorizes_a_prime_as_itself() {
+        assert_eq!(factorize(97), vec![97]);
+    }
+
+    #[test]
+    fn factorizes_a_power_of_two() {
+        assert_eq!(factorize(1024), vec![2; 10]);
+    }
+
+    #[test]
+    fn factorizes_a_small_composite() {
+        assert_eq!(factorize(60), vec![2, 2, 3, 5]);
+    }
+
+    #[test]
+    fn factorizes_a_semiprime_of_two_large_primes() {
+        // 4_000_000_007 * 4_000_000_009, both prime, well past the
+        // small-prime wheel and into Pollard's rho territory.
+        assert_eq!(
+            factorize(16_000_000_064_000_000_063),
+            vec![4_000_000_007, 4_000_000_009]
+        );
+    }
+
+    #[test]
+    fn product_of_factors_recovers_original_large_composite() {
+        let n = 100_003u64 * 100_019 * 100_043;
+        let factors = factorize(n);
+        assert_eq!(factors.iter().product::<u64>(), n);
+        assert_eq!(factors, vec![100_003, 100_019, 100_043]);
+    }
+}
+
+/// How many odd candidates a single sieve segment covers. Chosen small
+/// enough to keep memory use trivial (a few KiB of bits) while still
+/// being large relative to the gaps between primes, so segment
+/// boundaries rarely cost more than a handful of wasted cull passes.
+const SEGMENT_ODDS: u64 = 1 << 16;
+
+/// Bit-packed odds-only sieve of the single window `[lo, hi]`, where
+/// `lo` is odd: bit `i` of the result is set iff `lo + 2*i` is known
+/// composite. `base_primes` must contain every odd prime `p` with `p*p <=
+/// hi`, in ascending order, and not 2. `hi` is inclusive (rather than the more
+/// usual exclusive bound) so that a window ending at `u64::MAX` can be
+/// expressed without an off-the-end `hi = 2^64` that wouldn't fit in a
+/// `u64`.
+fn sieve_segment(lo: u64, hi: u64, base_primes: &[u64]) -> Vec<u32> {
+    let count = (hi - lo) / 2 + 1;
+    let mut bits = vec![0u32; (count as usize).div_ceil(32)];
+    for &p in base_primes {
+        if p * p > hi {
+            break;
+        }
+        let from = (p * p).max(lo);
+        let mut start = from + (p - from % p) % p;
+        if start % 2 == 0 {
+            start += p;
+        }
+        let mut m = start;
+        while m <= hi {
+            let idx = ((m - lo) / 2) as usize;
+            bits[idx / 32] |= 1 << (idx % 32);
+            m = match m.checked_add(2 * p) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+    }
+    bits
+}
+
+/// Bit-packed odds-only sieve of every odd prime up to and including
+/// `bound` (2 is not included). The base primes sieving `[3, bound]` needs are exactly the
+/// primes up to `sqrt(bound)`, so this recurses on the (much smaller)
+/// square root to bootstrap [`sieve_segment`]'s `base_primes` argument.
+fn primes_up_to_vec(bound: u64) -> Vec<u64> {
+    if bound < 3 {
+        return Vec::new();
+    }
+    let base_primes = if bound < 9 {
+        Vec::new()
+    } else {
+        primes_up_to_vec(usqrt(bound))
+    };
+    let bits = sieve_segment(3, bound, &base_primes);
+    (0..bits.len() * 32)
+        .map(|i| 3 + 2 * i as u64)
+        .take_while(|&n| n <= bound)
+        .zip(0..)
+        .filter(|&(_, i)| bits[i / 32] & (1 << (i % 32)) == 0)
+        .map(|(n, _)| n)
+        .collect()
+}
+
+/// Iterator state for the odd primes `primes_up_to` yields after 2: the
+/// current segment's bits plus where to resume once they're exhausted.
+struct SegmentedOdds {
+    limit: u64,
+    base_primes: Vec<u64>,
+    seg_lo: u64,
+    bits: Vec<u32>,
+    idx: usize,
+    count: usize,
+    /// Whether the segment currently loaded is the last one. Tracked
+    /// explicitly (rather than comparing `seg_lo` against `limit`)
+    /// because once `limit` is within `2*SEGMENT_ODDS` of `u64::MAX`,
+    /// the start of the segment after the last one does not fit in a
+    /// `u64`, so `seg_lo` is never advanced past `limit`.
+    done: bool,
+}
+
+impl SegmentedOdds {
+    fn new(limit: u64, base_primes: Vec<u64>) -> Self {
+        let mut odds = SegmentedOdds {
+            limit,
+            base_primes,
+            seg_lo: 3,
+            bits: Vec::new(),
+            idx: 0,
+            count: 0,
+            done: limit < 3,
+        };
+        if !odds.done {
+            odds.fill_segment();
+        }
+        odds
+    }
+
+    /// Sieves `[seg_lo, hi]`, where `hi` is `seg_lo + 2*SEGMENT_ODDS - 1`
+    /// clamped to `limit`. Both the window width and the clamp are
+    /// computed in `u128` so that a `limit` near `u64::MAX` doesn't
+    /// collapse `hi` and `seg_lo` to the same saturated `u64` value;
+    /// `done` likewise comes from that `u128` comparison instead of a
+    /// saturated bound, and `hi` itself is inclusive so it never needs
+    /// to represent the unrepresentable `u64::MAX + 1`.
+    fn fill_segment(&mut self) {
+        let lo = self.seg_lo as u128;
+        let hi = (lo + 2 * SEGMENT_ODDS as u128 - 1).min(self.limit as u128);
+        self.count = ((hi - lo) / 2 + 1) as usize;
+        self.bits = sieve_segment(self.seg_lo, hi as u64, &self.base_primes);
+        self.idx = 0;
+        self.done = hi >= self.limit as u128;
+    }
+}
+
+impl Iterator for SegmentedOdds {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            while self.idx < self.count {
+                let i = self.idx;
+                self.idx += 1;
+                if self.bits[i / 32] & (1 << (i % 32)) == 0 {
+                    return Some(self.seg_lo + 2 * i as u64);
+                }
+            }
+            if self.done {
+                return None;
+            }
+            self.seg_lo += 2 * SEGMENT_ODDS;
+            self.fill_segment();
+        }
+    }
+}
+
+/// Returns an iterator over every prime `p <= limit`, backed by a
+/// segmented, bit-packed Sieve of Eratosthenes: each segment culls a
+/// fixed-size window of odd candidates using the base primes up to
+/// `sqrt(limit)`, so the segment buffer stays the same size no matter
+/// how large `limit` is. The base primes themselves are all held in
+/// memory, so their share grows with `sqrt(limit)`.
+/// Time complexity: O(limit * log(log(limit)))
+/// # Examples
+/// ```
+/// use next_prime::primes_up_to;
+/// assert_eq!(
+///     primes_up_to(20).collect::<Vec<u64>>(),
+///     vec![2, 3, 5, 7, 11, 13, 17, 19]
+/// );
+/// ```
+pub fn primes_up_to(limit: u64) -> impl Iterator<Item = u64> {
+    let base_primes = if limit < 9 {
+        Vec::new()
+    } else {
+        primes_up_to_vec(usqrt(limit))
+    };
+    (limit >= 2)
+        .then_some(2)
+        .into_iter()
+        .chain(SegmentedOdds::new(limit, base_primes))
+}
+
+#[cfg(test)]
+mod primes_up_to_tests {
+    use super::{is_prime, primes_up_to};
+
+    #[test]
+    fn matches_known_small_primes() {
+        assert_eq!(
+            primes_up_to(20).collect::<Vec<u64>>(),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+    }
+
+    #[test]
+    fn edge_case_zero_and_one() {
+        assert_eq!(primes_up_to(0).collect::<Vec<u64>>(), Vec::<u64>::new());
+        assert_eq!(primes_up_to(1).collect::<Vec<u64>>(), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn edge_case_two() {
+        assert_eq!(primes_up_to(2).collect::<Vec<u64>>(), vec![2]);
+    }
+
+    #[test]
+    fn matches_is_prime_across_several_sieve_segments() {
+        // `SEGMENT_ODDS` is 2^16, so 1_000_000 forces several segment
+        // boundaries to be crossed.
+        let limit = 1_000_000u64;
+        let expected = (2..=limit).filter(|&n| is_prime(n)).collect::<Vec<u64>>();
+        assert_eq!(primes_up_to(limit).collect::<Vec<u64>>(), expected);
+    }
+
+    #[test]
+    fn terminates_when_the_final_segment_abuts_u64_max() {
+        // Regression test: when `seg_lo` lands within one segment of
+        // `u64::MAX`, the old `saturating_add`-based bounds collapsed
+        // `hi` and the next `seg_lo` to the same sentinel value, so
+        // `seg_lo > limit` never fired and the iterator spun forever.
+        let limit = u64::MAX;
+        let seg_lo = limit - 100; // odd, well within the final segment
+        let mut odds = super::SegmentedOdds {
+            limit,
+            base_primes: Vec::new(),
+            seg_lo,
+            bits: Vec::new(),
+            idx: 0,
+            count: 0,
+            done: false,
+        };
+        odds.fill_segment();
+        assert!(odds.done);
+        // Must exhaust and return `None` rather than hang.
+        assert!(odds.by_ref().all(|p| p <= limit));
+        assert_eq!(odds.next(), None);
+    }
+}
+
+/// The first few primes, returned directly by [`nth_prime`] rather than
+/// relying on the analytic bound below, which only exceeds the true
+/// `k`-th prime starting at `k = 6`.
+const FIRST_PRIMES: [u64; 5] = [2, 3, 5, 7, 11];
+
+/// Returns the `k`-th prime, 1-indexed (`nth_prime(1) == 2`).
+///
+/// For `k < 6` this is a table lookup. Otherwise it sieves up to the
+/// prime-counting estimate `k * (ln(k) + ln(ln(k)))`, which provably
+/// exceeds the true `k`-th prime once `k >= 6`, so a single sieve pass
+/// always finds it; the sieve is only widened if that ever turns out
+/// not to hold.
+/// Time complexity: O(bound * log(log(bound))), where `bound` is the
+/// analytic estimate above
+/// # Examples
+/// ```
+/// use next_prime::nth_prime;
+/// assert_eq!(nth_prime(1), 2);
+/// assert_eq!(nth_prime(6), 13);
+/// ```
+pub fn nth_prime(k: u64) -> u64 {
+    assert!(k >= 1, "k must be at least 1");
+    if let Some(&p) = FIRST_PRIMES.get((k - 1) as usize) {
+        return p;
+    }
+    let kf = k as f64;
+    let mut bound = (kf * (kf.ln() + kf.ln().ln())).ceil() as u64;
+    loop {
+        let primes = primes_up_to(bound).collect::<Vec<u64>>();
+        debug_assert!(
+            primes.len() as u64 >= k,
+            "analytic bound {bound} should exceed the {k}-th prime for k >= 6"
+        );
+        if let Some(&p) = primes.get((k - 1) as usize) {
+            return p;
+        }
+        bound *= 2;
+    }
+}
+
+#[cfg(test)]
+mod nth_prime_tests {
+    use super::nth_prime;
+
+    #[test]
+    fn matches_table_for_small_k() {
+        assert_eq!(
+            (1..=5).map(nth_prime).collect::<Vec<u64>>(),
+            vec![2, 3, 5, 7, 11]
+        );
+    }
+
+    #[test]
+    fn matches_known_primes_past_the_table() {
+        assert_eq!(nth_prime(6), 13);
+        assert_eq!(nth_prime(100), 541);
+        assert_eq!(nth_prime(1_000), 7919);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_on_zeroth_prime() {
+        nth_prime(0);
+    }
+}